@@ -42,6 +42,15 @@ impl hedgehog::Model for FlurryModel {
             }
         }
     }
+
+    fn commutes(&self, a: &Self::Op, b: &Self::Op) -> bool {
+        fn key(op: &FlurryOp) -> u64 {
+            match op {
+                FlurryOp::Get(k) | FlurryOp::Set(k, _) | FlurryOp::Rm(k) => *k,
+            }
+        }
+        key(a) != key(b)
+    }
 }
 
 #[derive(Clone)]
@@ -1,4 +1,4 @@
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
 use hedgehog::runner;
 use rand::prelude::Distribution;
@@ -46,6 +46,15 @@ impl hedgehog::Model for KvModel {
             }
         }
     }
+
+    fn commutes(&self, a: &Self::Op, b: &Self::Op) -> bool {
+        fn key(op: &KvOp) -> &str {
+            match op {
+                KvOp::Get(k) | KvOp::Set(k, _) | KvOp::Rm(k) => k,
+            }
+        }
+        key(a) != key(b)
+    }
 }
 
 struct KvSystem {
@@ -120,18 +129,10 @@ fn main() {
     let cpus = 1;
     println!("Using {} threads", cpus);
 
-    std::thread::spawn(|| loop {
-        if let Some(stats) = memory_stats::memory_stats() {
-            if stats.physical_mem > 10_000_000_000 {
-                eprintln!("Hedgehow exeeded the memory budget");
-                std::process::exit(1);
-            }
-            std::thread::sleep(Duration::from_secs(1));
-        } else {
-            eprintln!("Could not read the memory stats, you're on your own");
-            break;
-        }
-    });
+    // Bound the visited-state cache instead of relying on a memory watchdog:
+    // the checker degrades into re-exploring evicted subtrees rather than
+    // ballooning in memory.
+    const CACHE_CAPACITY: usize = 2_000_000;
 
     for _ in 0..100 / cpus {
         let mut hists = Vec::new();
@@ -150,14 +151,16 @@ fn main() {
                 s.spawn(move || {
                     let checking = Instant::now();
 
-                    let mut checker = hedgehog::Checker::new(hist);
+                    let mut checker =
+                        hedgehog::Checker::with_cache_capacity(hist, CACHE_CAPACITY);
 
                     let res = checker.check_linearizability();
 
                     println!(
-                        "Trace produced in {:?} and checked in {:?}: {}",
+                        "Trace produced in {:?} and checked in {:?} ({} cache evictions): {}",
                         prod_dur,
                         checking.elapsed(),
+                        checker.cache_evictions(),
                         if res { "OK" } else { "NON-LINEARIZABLE" }
                     );
                 });
@@ -1,4 +1,8 @@
-use std::iter;
+use core::iter;
+
+use alloc::vec::Vec;
+
+use crate::fingerprint::Fingerprint;
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct BitVec {
@@ -41,10 +45,27 @@ impl BitVec {
         }
         self.hash ^= *block;
     }
+
+    /// The running hash already maintained incrementally in `set`, cheap but
+    /// only 64 bits wide; prefer [`BitVec::fingerprint128`] as a `Fingerprint`
+    /// half, since a single `u64` collapses to a constant when paired with a
+    /// fixed second half.
+    pub fn fingerprint(&self) -> u64 {
+        self.hash
+    }
+
+    /// A full 128-bit fingerprint of every block in `inner`, for use as one
+    /// half of a combined `Fingerprint`. Unlike `fingerprint()`, this depends
+    /// on the whole bitvec rather than just the incrementally-maintained
+    /// running hash, so it doesn't leave either `Fingerprint` half constant
+    /// across different linearization bitmasks.
+    pub fn fingerprint128(&self) -> Fingerprint {
+        Fingerprint::of(&self.inner)
+    }
 }
 
-impl std::hash::Hash for BitVec {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+impl core::hash::Hash for BitVec {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         state.write_u64(self.hash);
     }
 }
@@ -0,0 +1,60 @@
+use core::hash::{Hash, Hasher};
+
+/// A minimal FNV-1a hasher. `std`'s `DefaultHasher` isn't available without
+/// `std`, and this crate's core types need to hash under `no_std` too.
+struct FnvHasher(u64);
+
+impl FnvHasher {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    fn seeded(seed: u64) -> Self {
+        Self(Self::OFFSET_BASIS ^ seed)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// A 128-bit fingerprint used as a cheap stand-in for an exact cache key when
+/// checking linearizability.
+///
+/// Two configurations that hash to the same `Fingerprint` are assumed to be
+/// the same configuration. Collisions are possible but, at 128 bits over the
+/// tens of millions of configurations a checker run explores, negligibly
+/// unlikely; see `Checker::with_exact_cache` for a slower, collision-free
+/// fallback for paranoid runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Fingerprint(pub u64, pub u64);
+
+impl Fingerprint {
+    pub const ZERO: Fingerprint = Fingerprint(0, 0);
+
+    /// Mix `other` into `self`, producing a fingerprint that depends on both.
+    pub fn combine(self, other: Fingerprint) -> Fingerprint {
+        Fingerprint(
+            self.0.wrapping_mul(3).wrapping_add(other.0),
+            self.1 ^ other.1.rotate_left(32).wrapping_mul(0x9E3779B97F4A7C15),
+        )
+    }
+
+    /// Fingerprint an arbitrary `Hash` value by feeding it into two
+    /// independently-seeded 64-bit hashers and pairing up their outputs.
+    pub fn of<T: Hash>(value: &T) -> Fingerprint {
+        let mut h0 = FnvHasher::seeded(0);
+        let mut h1 = FnvHasher::seeded(0x9E3779B97F4A7C15);
+        value.hash(&mut h0);
+        value.hash(&mut h1);
+        Fingerprint(h0.finish(), h1.finish())
+    }
+}
@@ -1,14 +1,40 @@
-use std::{collections::HashSet, hash::Hash};
+//! Core model-checking types (`Model`, `Hist`, `Checker`, ...) build under
+//! `#![no_std]` with only `alloc`, so they can be linked into `no_std` test
+//! harnesses (kernel modules, embedded firmware) fed by custom-instrumented
+//! histories. The thread-spawning [`runner`] module needs a real OS and is
+//! only available behind the default-on `std` feature.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::hash::Hash;
+use hashbrown::HashSet;
 
 pub mod bitvec;
+pub mod fingerprint;
+#[cfg(feature = "std")]
 pub mod runner;
 
+use fingerprint::Fingerprint;
+
 pub trait Model: Sized {
-    type Op: Clone + std::fmt::Debug;
-    type Value: Clone + Eq + std::fmt::Debug;
+    type Op: Clone + core::fmt::Debug;
+    type Value: Clone + Eq + core::fmt::Debug;
 
     fn initial() -> Self;
     fn apply(&self, op: &Self::Op) -> (Self, Self::Value);
+
+    /// Whether applying `a` then `b` to `self` is guaranteed to produce the same
+    /// resulting state and the same two return values as applying `b` then `a`.
+    ///
+    /// This is only ever used as a hint to prune equivalent interleavings during
+    /// linearizability checking, so returning `false` (the default) is always
+    /// sound, just potentially slower: it only needs to hold for states actually
+    /// reachable during the check, not for every possible `Self`.
+    fn commutes(&self, _a: &Self::Op, _b: &Self::Op) -> bool {
+        false
+    }
 }
 
 #[derive(Debug)]
@@ -201,16 +227,78 @@ impl<'a, M: Model> IntoIterator for &'a Hist<M> {
 
 type Linbits = crate::bitvec::BitVec; // TODO: use u64
 
+/// A visited-configuration set bounded to roughly `capacity` live entries.
+///
+/// Entries are kept in two generations, "young" and "old". Once "young" fills
+/// up, "old" is dropped wholesale and "young" becomes the new "old" — a
+/// generational sweep, like a young-generation GC, rather than per-entry LRU
+/// bookkeeping. Evicting a cached key never affects correctness, only cost: a
+/// re-visited configuration just gets re-explored instead of being recognized
+/// as already-seen.
+struct BoundedCache<T> {
+    young: HashSet<T>,
+    old: HashSet<T>,
+    generation_capacity: usize,
+    evictions: u64,
+}
+
+impl<T: Eq + Hash> BoundedCache<T> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            young: HashSet::new(),
+            old: HashSet::new(),
+            generation_capacity: (capacity / 2).max(1),
+            evictions: 0,
+        }
+    }
+
+    /// Record `key` as visited. Returns `true` if it wasn't already present
+    /// (mirroring `HashSet::insert`).
+    fn insert(&mut self, key: T) -> bool {
+        if self.young.contains(&key) || self.old.contains(&key) {
+            return false;
+        }
+        self.young.insert(key);
+        if self.young.len() >= self.generation_capacity {
+            self.evictions += self.old.len() as u64;
+            self.old = core::mem::replace(&mut self.young, HashSet::new());
+        }
+        true
+    }
+
+    fn len(&self) -> usize {
+        self.young.len() + self.old.len()
+    }
+
+    fn evictions(&self) -> u64 {
+        self.evictions
+    }
+}
+
+/// The set of already-visited `(lined, state)` configurations, keyed either by
+/// a cheap 128-bit `Fingerprint` (the default) or by the exact key (for
+/// paranoid runs where a hash collision cannot be tolerated).
+enum Cache<M> {
+    Fingerprints(BoundedCache<Fingerprint>),
+    Exact(BoundedCache<(Linbits, M)>),
+}
+
 pub struct Checker<M: Model> {
     hist: Hist<M>,
     lined: Linbits,
-    calls: Vec<(usize, M)>,
-    cache: HashSet<(Linbits, M)>,
+    calls: Vec<(usize, M, Vec<usize>)>,
+    cache: Cache<M>,
+    /// Invokes skipped by `commutes_with_smaller_peers` since the last actual
+    /// linearization, kept so they still get one real attempt. Each backtrack
+    /// point in `calls` saves the list as it stood and restores it when
+    /// popped. See the doc comment on `commutes_with_smaller_peers` for why
+    /// this is needed.
+    sleeping: Vec<usize>,
 }
 
 impl<M> Checker<M>
 where
-    M: Model + Clone + Eq + Hash + std::fmt::Debug,
+    M: Model + Clone + Eq + Hash + core::fmt::Debug,
 {
     pub fn new(hist: Hist<M>) -> Self {
         let len = hist.len();
@@ -218,8 +306,143 @@ where
             hist,
             lined: Linbits::from_elem(false, len / 2),
             calls: Vec::new(),
-            cache: HashSet::new(),
+            cache: Cache::Fingerprints(BoundedCache::new(usize::MAX)),
+            sleeping: Vec::new(),
+        }
+    }
+
+    /// Like [`Checker::new`], but caches visited configurations by their exact
+    /// `(Linbits, M)` key instead of a 128-bit fingerprint. This costs the full
+    /// per-node `M` clone the fingerprint cache was built to avoid, but is
+    /// immune to the (negligible but nonzero) chance of a fingerprint collision
+    /// causing a genuinely-new configuration to be mistaken for a visited one.
+    pub fn with_exact_cache(hist: Hist<M>) -> Self {
+        Self {
+            cache: Cache::Exact(BoundedCache::new(usize::MAX)),
+            ..Self::new(hist)
+        }
+    }
+
+    /// Like [`Checker::new`], but bounds the visited-configuration cache to
+    /// roughly `max_entries` live entries instead of growing it without bound.
+    /// Once the budget is hit, old entries are evicted (see [`BoundedCache`]);
+    /// this trades CPU (re-exploring evicted subtrees) for a fixed memory
+    /// envelope, so a long-running check degrades gracefully instead of
+    /// needing an external memory watchdog to abort it.
+    pub fn with_cache_capacity(hist: Hist<M>, max_entries: usize) -> Self {
+        Self {
+            cache: Cache::Fingerprints(BoundedCache::new(max_entries)),
+            ..Self::new(hist)
+        }
+    }
+
+    /// The number of configurations currently held in the visited-state cache.
+    pub fn cache_len(&self) -> usize {
+        match &self.cache {
+            Cache::Fingerprints(c) => c.len(),
+            Cache::Exact(c) => c.len(),
+        }
+    }
+
+    /// How many cache entries have been evicted to stay within budget, across
+    /// the lifetime of this checker. Always `0` for an unbounded cache.
+    pub fn cache_evictions(&self) -> u64 {
+        match &self.cache {
+            Cache::Fingerprints(c) => c.evictions(),
+            Cache::Exact(c) => c.evictions(),
+        }
+    }
+
+    /// True if at least one still-pending Invoke precedes `eid` (an un-lifted
+    /// Invoke) in the remaining history with a smaller `call_id`, and `eid`
+    /// commutes, under `s`, with every such Invoke. When that holds, trying `eid` next is redundant *if*
+    /// that smaller-`call_id` peer goes on to linearize first as assumed — but
+    /// nothing guarantees it does (it may itself be stuck behind a call that
+    /// hasn't happened yet). So a pruned `eid` is only deferred, not dropped:
+    /// it's recorded in `sleeping` and retried, under whatever state `s` is
+    /// current when the DFS next gets stuck, before that's reported as a dead
+    /// end. See `check_linearizability`.
+    fn commutes_with_smaller_peers(&self, s: &M, eid: usize) -> bool {
+        let (op, call_id) = match self.hist.get_from_eid(eid) {
+            Event::Invoke { op, call_id, .. } => (op, *call_id),
+            Event::Ret { .. } => unreachable!("Invoke event"),
+        };
+
+        let mut cur = self.hist.first_eid();
+        let mut has_smaller_peer = false;
+        while let Some(peer_eid) = cur {
+            if peer_eid == eid {
+                break;
+            }
+            if let Event::Invoke {
+                op: peer_op,
+                call_id: peer_call_id,
+                ..
+            } = self.hist.get_from_eid(peer_eid)
+            {
+                if *peer_call_id < call_id {
+                    has_smaller_peer = true;
+                    if !s.commutes(peer_op, op) {
+                        return false;
+                    }
+                }
+            }
+            cur = self.hist.next_eid(peer_eid);
+        }
+        has_smaller_peer
+    }
+
+    /// Try to linearize the un-lifted Invoke at `eid` now, given the current
+    /// state `s`. On success, marks its call as linearized (pushes the
+    /// backtrack point, moving the current `sleeping` list into it, lifts it
+    /// out of the history, records the new
+    /// configuration as visited) and returns the resulting state; the caller
+    /// is then free to resume scanning from `self.hist.first_eid()`. Returns
+    /// `None` if the recorded return doesn't match applying `eid`'s op here,
+    /// or if the resulting configuration was already visited, in which case
+    /// nothing is mutated.
+    fn try_linearize(&mut self, s: &M, eid: usize) -> Option<M> {
+        let (lin, call_id, s2) = self.apply(s, eid);
+        if !lin {
+            return None;
         }
+
+        let mut lined2 = self.lined.clone();
+        lined2.set(call_id, true);
+
+        let unseen = match &mut self.cache {
+            Cache::Fingerprints(set) => {
+                let fp = lined2.fingerprint128().combine(Fingerprint::of(&s2));
+                set.insert(fp)
+            }
+            Cache::Exact(set) => set.insert((lined2, s2.clone())),
+        };
+        if !unseen {
+            return None;
+        }
+
+        let sleeping = core::mem::take(&mut self.sleeping);
+        self.calls.push((eid, s.clone(), sleeping));
+        self.lined.set(call_id, true);
+        self.hist.lift(eid);
+        Some(s2)
+    }
+
+    /// Try every `eid` deferred by `commutes_with_smaller_peers` against the
+    /// current (unchanged-since-deferral) state `s`. Each sleeping `eid`'s
+    /// real-time prerequisites were already satisfied when it was deferred
+    /// (the DFS had already scanned past them to reach `eid`), so it's always
+    /// sound to retry one here. Returns the new state as soon as one commits;
+    /// the entries not yet tried then go into that commit's backtrack point
+    /// and come back if it is popped. Entries that fail under this unchanged
+    /// `s` are dropped, and `sleeping` is empty when this returns `None`.
+    fn wake_sleeping(&mut self, s: &M) -> Option<M> {
+        while let Some(eid) = self.sleeping.pop() {
+            if let Some(s2) = self.try_linearize(s, eid) {
+                return Some(s2);
+            }
+        }
+        None
     }
 
     fn apply(&self, s: &M, eid: usize) -> (bool, usize, M) {
@@ -256,34 +479,41 @@ where
             if matches!(self.hist.get_from_eid(eid), Event::Invoke { .. }) {
                 let next_eid = self.hist.next_eid(eid).unwrap();
 
-                let (lin, call_id, s2) = self.apply(&s, eid);
-
-                if lin {
-                    let mut lined2 = self.lined.clone();
-                    lined2.set(call_id, true);
-                    let unseen = self.cache.insert((lined2, s2.clone()));
+                if self.commutes_with_smaller_peers(&s, eid) {
+                    self.sleeping.push(eid);
+                    eid = next_eid;
+                    continue;
+                }
 
-                    if unseen {
-                        self.calls.push((eid, s));
-                        s = s2;
-                        self.lined.set(call_id, true);
-                        self.hist.lift(eid);
-                        if let Some(next_eid) = self.hist.first_eid() {
-                            eid = next_eid;
-                        } else {
-                            break;
-                        }
-                    } else {
+                if let Some(s2) = self.try_linearize(&s, eid) {
+                    self.sleeping.clear();
+                    s = s2;
+                    if let Some(next_eid) = self.hist.first_eid() {
                         eid = next_eid;
+                    } else {
+                        break;
                     }
                 } else {
                     eid = next_eid;
-                };
+                }
+            } else if let Some(s2) = self.wake_sleeping(&s) {
+                // A call deferred earlier under this same `s` (because it
+                // looked redundant next to a smaller-id commuting peer) just
+                // turned out to be the only way forward after all: the peer
+                // it deferred to never got to linearize first, so nothing
+                // here was actually explored for us.
+                s = s2;
+                if let Some(next_eid) = self.hist.first_eid() {
+                    eid = next_eid;
+                } else {
+                    break;
+                }
             } else {
                 match self.calls.pop() {
                     None => return false,
-                    Some((eid2, s2)) => {
+                    Some((eid2, s2, sleeping2)) => {
                         let call_id = self.hist.get_call_id(eid2);
+                        self.sleeping = sleeping2;
 
                         self.lined.set(call_id, false);
                         self.hist.unlift(eid2);
@@ -298,3 +528,111 @@ where
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::BTreeMap;
+
+    #[derive(Debug, Clone, Eq, PartialEq, Hash)]
+    struct TinyKv {
+        inner: BTreeMap<u8, u8>,
+    }
+
+    #[derive(Debug, Clone)]
+    enum TinyOp {
+        Get(u8),
+        Set(u8, u8),
+    }
+
+    impl Model for TinyKv {
+        type Op = TinyOp;
+        type Value = Option<u8>;
+
+        fn initial() -> Self {
+            Self {
+                inner: BTreeMap::new(),
+            }
+        }
+
+        fn apply(&self, op: &Self::Op) -> (Self, Self::Value) {
+            match op {
+                TinyOp::Get(key) => (self.clone(), self.inner.get(key).copied()),
+                TinyOp::Set(key, val) => {
+                    let mut inner = self.inner.clone();
+                    inner.insert(*key, *val);
+                    (Self { inner }, None)
+                }
+            }
+        }
+
+        fn commutes(&self, a: &Self::Op, b: &Self::Op) -> bool {
+            fn key(op: &TinyOp) -> u8 {
+                match op {
+                    TinyOp::Get(k) | TinyOp::Set(k, _) => *k,
+                }
+            }
+            key(a) != key(b)
+        }
+    }
+
+    fn invoke(hist: &mut Hist<TinyKv>, op: TinyOp, call_id: usize) -> usize {
+        hist.push_back(Event::Invoke {
+            op,
+            ret_event: usize::MAX,
+            call_id,
+        })
+    }
+
+    fn complete(hist: &mut Hist<TinyKv>, inv_eid: usize, val: Option<u8>) {
+        let pos = hist.push_back(Event::Ret { val });
+        if let Event::Invoke { ret_event, .. } = hist.get_mut_from_eid(inv_eid) {
+            *ret_event = pos;
+        }
+    }
+
+    /// `Get(1)` (call 0) spans the whole history and can only be satisfied by
+    /// observing `Set(1, 2)` (call 2); `Set(0, 9)` (call 1, disjoint key)
+    /// returns before `Set(1, 2)` is even invoked. `Get(1)` and `Set(0, 9)`
+    /// are concurrent and commute (disjoint keys), so `Set(0, 9)` gets
+    /// pruned as redundant next to its smaller-call_id peer `Get(1)`. But
+    /// `Get(1)` can't itself linearize until `Set(1, 2)` has run, and
+    /// `Set(1, 2)` is real-time-blocked behind `Set(0, 9)` — so the only
+    /// peer the prune is deferring to can never go first. `Set(0, 9), Set(1,
+    /// 2), Get(1)` is a valid linearization; a checker that treats the prune
+    /// as a permanent skip instead of a deferral wrongly rejects it.
+    #[test]
+    fn commuting_prune_still_finds_a_three_way_linearization() {
+        let mut hist = Hist::with_capacity(6);
+
+        let get1 = invoke(&mut hist, TinyOp::Get(1), 0);
+        let set0 = invoke(&mut hist, TinyOp::Set(0, 9), 1); // concurrent with get1
+        complete(&mut hist, set0, None);
+        let set1 = invoke(&mut hist, TinyOp::Set(1, 2), 2); // after set0 returns
+        complete(&mut hist, set1, None);
+        complete(&mut hist, get1, Some(2)); // get1 observes set1
+
+        let mut checker = Checker::new(hist);
+        assert!(checker.check_linearizability());
+    }
+
+    /// `Set(0, 1)` and `Get(1)` are concurrent and commute under `TinyKv`
+    /// (disjoint keys), so `commutes_with_smaller_peers` prunes trying
+    /// `Get(1)` as the next linearization point until `Set(0, 1)` is placed.
+    /// That pruning must not paper over an otherwise non-linearizable
+    /// history: `Get(1)` here returns `Some(9)`, a value no `Set(1, _)`
+    /// ever produces, so the history is non-linearizable regardless of key
+    /// 0's ops and must still be rejected.
+    #[test]
+    fn commuting_concurrent_ops_do_not_mask_a_non_linearizable_history() {
+        let mut hist = Hist::with_capacity(4);
+
+        let set0 = invoke(&mut hist, TinyOp::Set(0, 1), 0);
+        let get1 = invoke(&mut hist, TinyOp::Get(1), 1);
+        complete(&mut hist, set0, None);
+        complete(&mut hist, get1, Some(9));
+
+        let mut checker = Checker::new(hist);
+        assert!(!checker.check_linearizability());
+    }
+}
@@ -1,5 +1,10 @@
 use std::{
-    sync::atomic::{AtomicBool, Ordering},
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     thread,
     time::Duration,
 };
@@ -89,38 +94,157 @@ where
 
     pub fn produce_history(self) -> Hist<M> {
         self.run();
+        events_to_hist(self.events, self.thread_count)
+    }
+}
 
-        let mut hist = Hist::with_capacity(self.events.capacity());
+/// Drain a queue of raw `Invoke`/`Ret` events (as recorded by [`Runner`] or
+/// [`AsyncRunner`]) into a [`Hist`], pairing up each `Invoke` with its
+/// participant's next `Ret`.
+fn events_to_hist<M: Model>(events: ArrayQueue<Event<M>>, participant_count: u32) -> Hist<M> {
+    let mut hist = Hist::with_capacity(events.capacity());
+
+    const INVALID: usize = usize::MAX;
+
+    let mut pending: Vec<usize> = std::iter::repeat(INVALID)
+        .take(participant_count as usize)
+        .collect();
+    // call_id must be unique and assigned in real-time (push) order: the
+    // checker's commutativity pruning relies on call_id ordering matching
+    // list order to identify the smaller-id peer among pending invocations.
+    let mut next_call_id: usize = 0;
+
+    for event in events {
+        match event {
+            Event::Invoke { op, tid } => {
+                let call_id = next_call_id;
+                next_call_id += 1;
+                let pos = hist.push_back(crate::Event::Invoke {
+                    op,
+                    ret_event: INVALID,
+                    call_id,
+                });
+                debug_assert_eq!(pending[tid as usize], INVALID);
+                pending[tid as usize] = pos;
+            }
+            Event::Ret { val, tid } => {
+                let pos = hist.push_back(crate::Event::Ret { val });
+                let inv = pending[tid as usize];
+                debug_assert_ne!(inv, INVALID);
+                if let crate::Event::Invoke { ret_event, .. } = hist.get_mut_from_eid(inv) {
+                    *ret_event = pos;
+                } else {
+                    unreachable!();
+                }
+                pending[tid as usize] = INVALID;
+            }
+        }
+    }
+    hist
+}
 
-        const INVALID: usize = usize::MAX;
+/// Like [`System`], but for data structures whose operations are driven with
+/// `async`/`.await` (async channels, `tokio`-based maps, "send and confirm"
+/// clients) instead of synchronous calls.
+pub trait AsyncSystem<M: Model>
+where
+    Self: Sized,
+{
+    type OpDist: Distribution<M::Op> + Send;
 
-        let mut pending: Vec<usize> = std::iter::repeat(INVALID)
-            .take(self.thread_count as usize)
-            .collect();
+    fn new_op_distr() -> Self::OpDist;
 
-        for event in self.events {
-            match event {
-                Event::Invoke { op, tid } => {
-                    let pos = hist.push_back(crate::Event::Invoke {
-                        op,
-                        ret_event: INVALID,
-                    });
-                    debug_assert_eq!(pending[tid as usize], INVALID);
-                    pending[tid as usize] = pos;
-                }
-                Event::Ret { val, tid } => {
-                    let pos = hist.push_back(crate::Event::Ret { val });
-                    let inv = pending[tid as usize];
-                    debug_assert_ne!(inv, INVALID);
-                    if let crate::Event::Invoke { ret_event, .. } = hist.get_mut_from_eid(inv) {
-                        *ret_event = pos;
-                    } else {
-                        unreachable!();
-                    }
-                    pending[tid as usize] = INVALID;
+    fn initial() -> Self;
+
+    // Spelled out as `-> impl Future + Send` rather than `async fn` so the
+    // returned future keeps a `Send` bound: `produce_history` boxes each
+    // task's future as `Pin<Box<dyn Future<Output = ()> + Send>>` to hand off
+    // to an arbitrary executor, which `async fn`'s inferred return type can't
+    // guarantee.
+    fn apply(&self, op: M::Op) -> impl Future<Output = M::Value> + Send;
+}
+
+/// Like [`Runner`], but exercises an [`AsyncSystem`] by scheduling
+/// `task_count` concurrent tasks on a caller-provided async executor instead
+/// of spawning OS threads.
+pub struct AsyncRunner<M: Model, S: AsyncSystem<M>> {
+    events: ArrayQueue<Event<M>>,
+    system: S,
+    task_count: u32,
+    events_per_thread: u32,
+}
+
+impl<M, S> AsyncRunner<M, S>
+where
+    M: Model + 'static,
+    S: AsyncSystem<M> + Send + Sync + 'static,
+    M::Value: Send,
+    M::Op: Send,
+    M::Op: std::fmt::Debug,
+    M::Value: std::fmt::Debug,
+    M: std::fmt::Debug,
+{
+    pub fn new(task_count: u32, events_per_thread: u32) -> Self {
+        Self {
+            events: ArrayQueue::new(task_count as usize * events_per_thread as usize * 2),
+            task_count,
+            events_per_thread,
+            system: S::initial(),
+        }
+    }
+
+    /// Run `task_count` tasks, each performing `events_per_thread` operations
+    /// against the shared system, and return the resulting history.
+    ///
+    /// `spawn` hands each task's future off to whatever executor the caller is
+    /// running, so this crate doesn't need to depend on one; the returned
+    /// future is simply awaited afterwards to wait for that task to finish.
+    /// `tokio::spawn` itself returns a `JoinHandle<()>`, whose `Future::Output`
+    /// is `Result<(), JoinError>` rather than `()`, so it needs a thin adapter:
+    /// `|fut| { let h = tokio::spawn(fut); async move { h.await.unwrap(); } }`.
+    /// Real-time order is preserved exactly as in
+    /// [`Runner`]: `Invoke` is recorded before `.await`ing the operation,
+    /// `Ret` only once it resolves.
+    pub async fn produce_history<Sp, H>(self, spawn: Sp) -> Hist<M>
+    where
+        Sp: Fn(Pin<Box<dyn Future<Output = ()> + Send>>) -> H,
+        H: Future<Output = ()>,
+    {
+        let events = Arc::new(self.events);
+        let system = Arc::new(self.system);
+
+        let mut handles = Vec::with_capacity(self.task_count as usize);
+        for tid in 0..self.task_count {
+            let events = Arc::clone(&events);
+            let system = Arc::clone(&system);
+            let events_per_thread = self.events_per_thread;
+
+            let task: Pin<Box<dyn Future<Output = ()> + Send>> = Box::pin(async move {
+                let dist = S::new_op_distr();
+                let mut rng = rand::rngs::SmallRng::from_entropy();
+
+                for _ in 0..events_per_thread {
+                    let op = dist.sample(&mut rng);
+                    events
+                        .push(Event::Invoke {
+                            op: op.clone(),
+                            tid,
+                        })
+                        .unwrap();
+                    let res = system.apply(op).await;
+                    events.push(Event::Ret { val: res, tid }).unwrap();
                 }
-            }
+            });
+
+            handles.push(spawn(task));
         }
-        hist
+
+        for handle in handles {
+            handle.await;
+        }
+
+        let events = Arc::try_unwrap(events)
+            .unwrap_or_else(|_| unreachable!("all tasks have been awaited"));
+        events_to_hist(events, self.task_count)
     }
 }